@@ -1,17 +1,18 @@
 use log::debug;
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
     fmt,
     fs::File,
+    hash::{Hash, Hasher},
     io::BufReader,
-    rc::{Rc, Weak},
+    rc::Rc,
 };
 use xml::reader::{EventReader, XmlEvent};
 
 #[derive(Debug)]
 enum ParseEvent {
-    Start(String),
+    Start(String, Vec<String>),
     End(String),
 }
 struct EventSource<T>(EventReader<T>)
@@ -29,8 +30,14 @@ where
         let event;
         loop {
             match self.0.next() {
-                Ok(XmlEvent::StartElement { name, .. }) => {
-                    event = Some(ParseEvent::Start(name.local_name));
+                Ok(XmlEvent::StartElement {
+                    name, attributes, ..
+                }) => {
+                    let attributes = attributes
+                        .into_iter()
+                        .map(|a| a.name.local_name)
+                        .collect();
+                    event = Some(ParseEvent::Start(name.local_name, attributes));
                     break;
                 }
                 Ok(XmlEvent::EndElement { name, .. }) => {
@@ -54,10 +61,52 @@ where
     }
 }
 
+// Per-edge occurrence counts, used to infer a DTD-like cardinality marker.
+#[derive(Debug)]
+struct Cardinality {
+    seen: u32,
+    max: u32,
+}
+
+impl Cardinality {
+    fn new() -> Self {
+        Cardinality { seen: 0, max: 0 }
+    }
+
+    // Folds in the count observed for one parent instance.
+    fn record(&mut self, count: u32) {
+        self.seen += 1;
+        self.max = self.max.max(count);
+    }
+
+    fn marker(&self, parent_instances: u32) -> &'static str {
+        let optional = self.seen < parent_instances;
+        let many = self.max > 1;
+
+        match (optional, many) {
+            (true, true) => "*",
+            (true, false) => "?",
+            (false, true) => "+",
+            (false, false) => "",
+        }
+    }
+}
+
+// Whole-document cardinality data, keyed by tag since thinning collapses repeated parent instances into one edge.
+#[derive(Debug, Default)]
+struct Cardinalities {
+    parent_instances: HashMap<String, u32>,
+    edges: HashMap<(String, String), Cardinality>,
+}
+
+// No parent pointer, so identical subtrees (same tag + children) can share one `Rc` (see `NodeCache`).
 #[derive(Debug)]
 struct Node {
-    children: RefCell<HashMap<String, Rc<Self>>>,
-    parent: Option<Weak<Self>>,
+    tag: String,
+    children: HashMap<String, Rc<Self>>,
+    cardinalities: Rc<RefCell<Cardinalities>>,
+    // Union of attribute local-names seen across every occurrence of this element.
+    attributes: RefCell<HashSet<String>>,
 }
 
 impl PartialEq for Node {
@@ -68,26 +117,35 @@ impl PartialEq for Node {
 
 impl fmt::Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.print(0))
+        write!(f, "{}", self.print("", 0))
     }
 }
 
 // for Display
 impl Node {
-    fn print(&self, depth: usize) -> String {
+    fn print(&self, own_tag: &str, depth: usize) -> String {
         let mut out = "".to_string();
+        let cardinalities = self.cardinalities.borrow();
 
-        for (name, node) in self.children.borrow().iter() {
+        for (name, node) in self.children.iter() {
             let indent = "  ".repeat(depth);
+            let marker = cardinalities
+                .edges
+                .get(&(own_tag.to_string(), name.clone()))
+                .map(|c| c.marker(*cardinalities.parent_instances.get(own_tag).unwrap_or(&0)))
+                .unwrap_or("");
+            let attributes = node.attribute_list();
 
-            if node.children.borrow().len() < 1 {
-                out.push_str(&format!("{}<{} />\n", indent, name))
+            if node.children.is_empty() {
+                out.push_str(&format!("{}<{}{}{} />\n", indent, name, marker, attributes))
             } else {
                 out.push_str(&format!(
-                    "{}<{}>\n{}{}</{}>\n",
+                    "{}<{}{}{}>\n{}{}</{}>\n",
                     indent,
                     name,
-                    node.print(depth + 1),
+                    marker,
+                    attributes,
+                    node.print(name, depth + 1),
                     indent,
                     name
                 ));
@@ -96,58 +154,410 @@ impl Node {
 
         out
     }
+
+    // e.g. " @changefreq @priority" (sorted by name), or "" when no attributes were observed
+    fn attribute_list(&self) -> String {
+        let mut attributes: Vec<String> = self.attributes.borrow().iter().cloned().collect();
+        attributes.sort();
+
+        attributes.iter().map(|a| format!(" @{}", a)).collect()
+    }
 }
 
-// Main implementation of the thin parsing logic
-fn parse<T>(source: &mut T) -> Rc<Node>
+// Serializes a `Node` as its children only, nested recursively as a JSON
+// object keyed by tag name. Cardinality and attribute data is derived from
+// the source document, not the tree shape, so it isn't round-tripped here.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Node {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.children.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Node {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let children = HashMap::deserialize(deserializer)?;
+
+        Ok(Node {
+            tag: String::new(),
+            children,
+            cardinalities: Rc::new(RefCell::new(Cardinalities::default())),
+            attributes: RefCell::new(HashSet::new()),
+        })
+    }
+}
+
+// Thinned shape of one element, accumulated across repeated occurrences before interning.
+#[derive(Default)]
+struct NodeBuilder {
+    children: HashMap<String, NodeBuilder>,
+    attributes: HashSet<String>,
+}
+
+impl NodeBuilder {
+    // Recursively unions `other` into `self` so repeated siblings thin into one shape.
+    fn merge(&mut self, other: NodeBuilder) {
+        self.attributes.extend(other.attributes);
+        for (tag, child) in other.children {
+            self.children.entry(tag).or_default().merge(child);
+        }
+    }
+}
+
+// Deduplicates `Node`s by tag and children shape, collapsing the tree into a DAG.
+struct NodeCache {
+    cardinalities: Rc<RefCell<Cardinalities>>,
+    nodes: HashMap<u64, Vec<Rc<Node>>>,
+    count: usize,
+}
+
+impl NodeCache {
+    fn new(cardinalities: Rc<RefCell<Cardinalities>>) -> Self {
+        NodeCache {
+            cardinalities,
+            nodes: HashMap::new(),
+            count: 0,
+        }
+    }
+
+    fn intern(&mut self, tag: String, children: HashMap<String, Rc<Node>>) -> Rc<Node> {
+        let hash = Self::structural_hash(&tag, &children);
+        let bucket = self.nodes.entry(hash).or_default();
+
+        if let Some(existing) = bucket
+            .iter()
+            .find(|node| Self::same_shape(node, &tag, &children))
+        {
+            return existing.clone();
+        }
+
+        let node = Rc::new(Node {
+            tag,
+            children,
+            cardinalities: self.cardinalities.clone(),
+            attributes: RefCell::new(HashSet::new()),
+        });
+        bucket.push(node.clone());
+        self.count += 1;
+
+        node
+    }
+
+    fn same_shape(node: &Node, tag: &str, children: &HashMap<String, Rc<Node>>) -> bool {
+        node.tag == tag
+            && node.children.len() == children.len()
+            && node.children.iter().all(|(tag, child)| {
+                children
+                    .get(tag)
+                    .is_some_and(|other_child| Rc::ptr_eq(child, other_child))
+            })
+    }
+
+    // Sorted `(tag, Rc::as_ptr(child))` pairs; children are already interned.
+    fn structural_hash(tag: &str, children: &HashMap<String, Rc<Node>>) -> u64 {
+        let mut pairs: Vec<(&str, usize)> = children
+            .iter()
+            .map(|(tag, child)| (tag.as_str(), Rc::as_ptr(child) as usize))
+            .collect();
+        pairs.sort_unstable();
+
+        let mut hasher = DefaultHasher::new();
+        tag.hash(&mut hasher);
+        pairs.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+// Converts a finished `NodeBuilder` into an interned `Node`, children first.
+fn intern(tag: String, builder: NodeBuilder, cache: &mut NodeCache) -> Rc<Node> {
+    let children = builder
+        .children
+        .into_iter()
+        .map(|(tag, child)| (tag.clone(), intern(tag, child, cache)))
+        .collect();
+
+    let node = cache.intern(tag, children);
+    node.attributes.borrow_mut().extend(builder.attributes);
+
+    node
+}
+
+// Thins the event stream into a `NodeBuilder` tree, interns it bottom-up into a DAG, and returns the number of distinct interned nodes (the synthetic root not included) alongside the tree.
+fn parse_interned<T>(source: &mut T) -> (Rc<Node>, usize)
 where
     T: Iterator<Item = ParseEvent>,
 {
+    let cardinalities = Rc::new(RefCell::new(Cardinalities::default()));
+    let mut cache = NodeCache::new(cardinalities.clone());
+
+    let mut root = NodeBuilder::default();
+    let mut stack: Vec<(String, NodeBuilder)> = Vec::new();
+
+    // Per-open-element child tag counts, one frame per currently open
+    // element, folded into `cardinalities` as each element closes.
+    let mut child_counts: Vec<HashMap<String, u32>> = vec![HashMap::new()];
+
+    for e in source {
+        match e {
+            ParseEvent::Start(name, attributes) => {
+                *child_counts.last_mut().unwrap().entry(name.clone()).or_insert(0) += 1;
+                child_counts.push(HashMap::new());
+
+                let mut builder = NodeBuilder::default();
+                builder.attributes.extend(attributes);
+                stack.push((name, builder));
+
+                debug!("> Entering node, depth {}", stack.len());
+            }
+            ParseEvent::End(name) => {
+                debug!("< Exiting node {}, depth {}", name, stack.len());
+
+                let counts = child_counts.pop().unwrap();
+                let mut cardinalities = cardinalities.borrow_mut();
+
+                *cardinalities.parent_instances.entry(name.clone()).or_insert(0) += 1;
+                for (child_tag, count) in counts {
+                    cardinalities
+                        .edges
+                        .entry((name.clone(), child_tag))
+                        .or_insert_with(Cardinality::new)
+                        .record(count);
+                }
+                drop(cardinalities);
+
+                let (_, finished) = stack.pop().unwrap();
+                let parent = stack.last_mut().map(|(_, builder)| builder).unwrap_or(&mut root);
+
+                parent.children.entry(name).or_default().merge(finished);
+            }
+        }
+    }
+
+    let children = root
+        .children
+        .into_iter()
+        .map(|(tag, builder)| (tag.clone(), intern(tag, builder, &mut cache)))
+        .collect();
+
     let root = Rc::new(Node {
-        children: RefCell::new(HashMap::new()),
-        parent: None,
+        tag: String::new(),
+        children,
+        cardinalities,
+        attributes: RefCell::new(HashSet::new()),
     });
-    let mut node = root.clone();
+
+    (root, cache.count)
+}
+
+fn parse<T>(source: &mut T) -> Rc<Node>
+where
+    T: Iterator<Item = ParseEvent>,
+{
+    parse_interned(source).0
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ThinEvent {
+    Enter(String),
+    Exit(String),
+}
+
+// A frame on the thinning stack: `Active` tracks which child tags have
+// already been emitted under this element, `Muted` marks a repeated
+// sibling subtree that is being skipped without emitting anything.
+enum ThinFrame {
+    Active(HashSet<String>),
+    Muted,
+}
+
+struct ThinEvents<T>
+where
+    T: Iterator<Item = ParseEvent>,
+{
+    source: T,
+    stack: Vec<ThinFrame>,
+}
+
+impl<T> Iterator for ThinEvents<T>
+where
+    T: Iterator<Item = ParseEvent>,
+{
+    type Item = ThinEvent;
+
+    fn next(&mut self) -> Option<ThinEvent> {
+        loop {
+            match self.source.next()? {
+                ParseEvent::Start(name, _) => match self.stack.last_mut().unwrap() {
+                    ThinFrame::Muted => self.stack.push(ThinFrame::Muted),
+                    ThinFrame::Active(seen) => {
+                        if seen.contains(&name) {
+                            self.stack.push(ThinFrame::Muted);
+                        } else {
+                            seen.insert(name.clone());
+                            self.stack.push(ThinFrame::Active(HashSet::new()));
+
+                            return Some(ThinEvent::Enter(name));
+                        }
+                    }
+                },
+                ParseEvent::End(name) => match self.stack.pop() {
+                    Some(ThinFrame::Active(_)) => return Some(ThinEvent::Exit(name)),
+                    Some(ThinFrame::Muted) => {}
+                    None => {}
+                },
+            }
+        }
+    }
+}
+
+// Streaming alternative to `parse`: yields an `Enter`/`Exit` pair the first
+// time a tag is seen under its current parent, and silently swallows
+// repeated siblings (and everything nested under them) instead of building
+// an `Rc<Node>` tree. Lets callers print or serialize the thinned shape
+// without ever holding the whole document in memory.
+fn parse_events<T>(source: T) -> impl Iterator<Item = ThinEvent>
+where
+    T: Iterator<Item = ParseEvent>,
+{
+    ThinEvents {
+        source,
+        stack: vec![ThinFrame::Active(HashSet::new())],
+    }
+}
+
+// A house structure to lint documents against: for each element tag with an
+// entry, the complete set of child tags an instance of it is expected to
+// have.
+#[derive(Debug, Default)]
+struct Schema {
+    required_children: HashMap<String, Vec<String>>,
+}
+
+impl Schema {
+    fn new() -> Self {
+        Schema::default()
+    }
+
+    fn require(mut self, tag: &str, children: &[&str]) -> Self {
+        self.required_children
+            .insert(tag.to_string(), children.iter().map(|c| c.to_string()).collect());
+        self
+    }
+}
+
+// One element instance that didn't match its `Schema` entry, identified by
+// the root-to-node path of tags leading to it.
+#[derive(Debug, PartialEq, Eq)]
+struct ValidationError {
+    path: Vec<String>,
+    missing: Vec<String>,
+    unexpected: Vec<String>,
+}
+
+// Lints the document against `schema`, reporting every element instance that
+// is missing a required child or has a child the schema didn't expect.
+// Elements with no entry in `schema` are not checked.
+fn parse_validated<T>(source: &mut T, schema: &Schema) -> Vec<ValidationError>
+where
+    T: Iterator<Item = ParseEvent>,
+{
+    let mut path: Vec<String> = Vec::new();
+    let mut children_seen: Vec<HashSet<String>> = vec![HashSet::new()];
+    let mut errors = Vec::new();
 
     for e in source {
         match e {
-            ParseEvent::Start(name) => {
-                // Create a new child if it doesn't exist
-                let child = node
-                    .children
-                    .borrow_mut()
-                    .entry(name.clone())
-                    .or_insert_with(|| {
-                        Rc::new(Node {
-                            children: RefCell::new(HashMap::new()),
-                            parent: Some(Rc::downgrade(&node)),
-                        })
-                    })
-                    .clone(); // Copy the Rc
-
-                node = child;
-
-                debug!(
-                    "> Entering node: {}, ref count: {} strong, {} weak",
-                    name,
-                    Rc::strong_count(&node),
-                    Rc::weak_count(&node)
-                );
+            ParseEvent::Start(name, _) => {
+                children_seen.last_mut().unwrap().insert(name.clone());
+                path.push(name);
+                children_seen.push(HashSet::new());
             }
             ParseEvent::End(name) => {
-                debug!(
-                    "< Exiting node {} ref count: {} strong, {} weak",
-                    name,
-                    Rc::strong_count(&node),
-                    Rc::weak_count(&node)
-                );
+                let seen = children_seen.pop().unwrap();
+
+                if let Some(required) = schema.required_children.get(&name) {
+                    let missing: Vec<String> = required
+                        .iter()
+                        .filter(|child| !seen.contains(*child))
+                        .cloned()
+                        .collect();
+                    let unexpected: Vec<String> = seen
+                        .iter()
+                        .filter(|child| !required.contains(child))
+                        .cloned()
+                        .collect();
 
-                node = node.parent.as_ref().unwrap().upgrade().unwrap();
+                    if !missing.is_empty() || !unexpected.is_empty() {
+                        errors.push(ValidationError {
+                            path: path.clone(),
+                            missing,
+                            unexpected,
+                        });
+                    }
+                }
+
+                path.pop();
             }
         }
     }
 
-    root
+    errors
+}
+
+// Output format selected with `--format {xml,json}`; defaults to `xml`.
+fn format_arg() -> String {
+    std::env::args()
+        .collect::<Vec<_>>()
+        .windows(2)
+        .find(|pair| pair[0] == "--format")
+        .map(|pair| pair[1].clone())
+        .unwrap_or_else(|| "xml".to_string())
+}
+
+// Mode selected with `--mode {tree,interned,events,validate}`; defaults to `tree`.
+fn mode_arg() -> String {
+    std::env::args()
+        .collect::<Vec<_>>()
+        .windows(2)
+        .find(|pair| pair[0] == "--mode")
+        .map(|pair| pair[1].clone())
+        .unwrap_or_else(|| "tree".to_string())
+}
+
+// Schema file selected with `--schema <path>` for `--mode validate`; falls
+// back to `sitemap_schema()` when not given.
+fn schema_arg() -> Option<String> {
+    std::env::args()
+        .collect::<Vec<_>>()
+        .windows(2)
+        .find(|pair| pair[0] == "--schema")
+        .map(|pair| pair[1].clone())
+}
+
+// Builds a `Schema` from a config file of `tag: child1,child2,...` lines
+// (blank lines and lines starting with `#` are ignored), e.g.:
+//     urlset: url
+//     url: loc
+fn load_schema(path: &str) -> Schema {
+    let contents = std::fs::read_to_string(path).unwrap();
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .fold(Schema::new(), |schema, line| {
+            let (tag, children) = line.split_once(':').unwrap();
+            let children: Vec<&str> = children.split(',').map(str::trim).collect();
+
+            schema.require(tag.trim(), &children)
+        })
 }
 
 fn main() {
@@ -158,9 +568,72 @@ fn main() {
     let parser = EventReader::new(file);
     let mut source = EventSource(parser);
 
-    let tree = parse(&mut source);
+    match mode_arg().as_str() {
+        "interned" => {
+            let (tree, count) = parse_interned(&mut source);
+            print_tree(&tree);
+            eprintln!("{} distinct nodes allocated", count);
+        }
+        "events" => {
+            for event in parse_events(source) {
+                match event {
+                    ThinEvent::Enter(tag) => println!("> {}", tag),
+                    ThinEvent::Exit(tag) => println!("< {}", tag),
+                }
+            }
+        }
+        "validate" => {
+            let schema = schema_arg()
+                .map(|path| load_schema(&path))
+                .unwrap_or_else(sitemap_schema);
+            let errors = parse_validated(&mut source, &schema);
+
+            if errors.is_empty() {
+                println!("No validation errors");
+            } else {
+                for error in errors {
+                    println!("{:?}", error);
+                }
+            }
+        }
+        mode => {
+            if mode != "tree" {
+                eprintln!("Unknown mode '{}', defaulting to tree", mode);
+            }
+            let tree = parse(&mut source);
+            print_tree(&tree);
+        }
+    }
+}
+
+fn print_tree(tree: &Rc<Node>) {
+    match format_arg().as_str() {
+        "json" => print_json(tree),
+        format => {
+            if format != "xml" {
+                eprintln!("Unknown format '{}', defaulting to xml", format);
+            }
+            println!("{}", tree);
+        }
+    }
+}
+
+// Default schema for `--mode validate` when `--schema <path>` isn't given: a
+// minimal house structure for a typical sitemap.
+fn sitemap_schema() -> Schema {
+    Schema::new()
+        .require("urlset", &["url"])
+        .require("url", &["loc"])
+}
 
-    println!("{}", tree);
+#[cfg(feature = "serde")]
+fn print_json(tree: &Rc<Node>) {
+    println!("{}", serde_json::to_string_pretty(&**tree).unwrap());
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_json(_tree: &Rc<Node>) {
+    eprintln!("JSON output requires the `serde` feature");
 }
 
 #[cfg(test)]
@@ -170,20 +643,20 @@ mod test {
 
     fn node(children: HashMap<&'static str, Rc<Node>>) -> Rc<Node> {
         Rc::new(Node {
-            children: RefCell::new(
-                children
-                    .iter()
-                    .map(|(k, v)| (k.to_string(), v.clone()))
-                    .collect(),
-            ),
-            parent: None,
+            tag: String::new(),
+            children: children
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+            cardinalities: Rc::new(RefCell::new(Cardinalities::default())),
+            attributes: RefCell::new(HashSet::new()),
         })
     }
 
     #[test]
     fn single_node() {
         let mut stream = vec![
-            ParseEvent::Start("parent".to_string()),
+            ParseEvent::Start("parent".to_string(), vec![]),
             ParseEvent::End("parent".to_string()),
         ]
         .into_iter();
@@ -199,8 +672,8 @@ mod test {
     #[test]
     fn single_node_with_single_child() {
         let mut stream = vec![
-            ParseEvent::Start("parent".to_string()),
-            ParseEvent::Start("child".to_string()),
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("child".to_string(), vec![]),
             ParseEvent::End("child".to_string()),
             ParseEvent::End("parent".to_string()),
         ]
@@ -220,9 +693,9 @@ mod test {
     #[test]
     fn list() {
         let mut stream = vec![
-            ParseEvent::Start("parent".to_string()),
-            ParseEvent::Start("child".to_string()),
-            ParseEvent::Start("grandchild".to_string()),
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("child".to_string(), vec![]),
+            ParseEvent::Start("grandchild".to_string(), vec![]),
             ParseEvent::End("grandchild".to_string()),
             ParseEvent::End("child".to_string()),
             ParseEvent::End("parent".to_string()),
@@ -245,10 +718,10 @@ mod test {
     #[test]
     fn node_with_two_different_children() {
         let mut stream = vec![
-            ParseEvent::Start("parent".to_string()),
-            ParseEvent::Start("son".to_string()),
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
             ParseEvent::End("son".to_string()),
-            ParseEvent::Start("daughter".to_string()),
+            ParseEvent::Start("daughter".to_string(), vec![]),
             ParseEvent::End("daughter".to_string()),
             ParseEvent::End("parent".to_string()),
         ]
@@ -269,10 +742,10 @@ mod test {
     #[test]
     fn node_with_uniform_children() {
         let mut stream = vec![
-            ParseEvent::Start("parent".to_string()),
-            ParseEvent::Start("son".to_string()),
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
             ParseEvent::End("son".to_string()),
-            ParseEvent::Start("son".to_string()),
+            ParseEvent::Start("son".to_string(), vec![]),
             ParseEvent::End("son".to_string()),
             ParseEvent::End("parent".to_string()),
         ]
@@ -292,13 +765,13 @@ mod test {
     #[test]
     fn node_with_uniform_children_and_granchildren() {
         let mut stream = vec![
-            ParseEvent::Start("parent".to_string()),
-            ParseEvent::Start("son".to_string()),
-            ParseEvent::Start("grandson".to_string()),
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("grandson".to_string(), vec![]),
             ParseEvent::End("grandson".to_string()),
             ParseEvent::End("son".to_string()),
-            ParseEvent::Start("son".to_string()),
-            ParseEvent::Start("granddaughter".to_string()),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("granddaughter".to_string(), vec![]),
             ParseEvent::End("granddaughter".to_string()),
             ParseEvent::End("son".to_string()),
             ParseEvent::End("parent".to_string()),
@@ -322,23 +795,23 @@ mod test {
     #[test]
     fn complex_tree() {
         let mut stream = vec![
-            ParseEvent::Start("parent".to_string()),
-            ParseEvent::Start("son".to_string()),
-            ParseEvent::Start("grandson".to_string()),
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("grandson".to_string(), vec![]),
             ParseEvent::End("grandson".to_string()),
-            ParseEvent::Start("granddaughter".to_string()),
+            ParseEvent::Start("granddaughter".to_string(), vec![]),
             ParseEvent::End("granddaughter".to_string()),
             ParseEvent::End("son".to_string()),
-            ParseEvent::Start("son".to_string()),
-            ParseEvent::Start("granddaughter".to_string()),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("granddaughter".to_string(), vec![]),
             ParseEvent::End("granddaughter".to_string()),
-            ParseEvent::Start("granddaughter".to_string()),
+            ParseEvent::Start("granddaughter".to_string(), vec![]),
             ParseEvent::End("granddaughter".to_string()),
             ParseEvent::End("son".to_string()),
-            ParseEvent::Start("daughter".to_string()),
-            ParseEvent::Start("grandson".to_string()),
+            ParseEvent::Start("daughter".to_string(), vec![]),
+            ParseEvent::Start("grandson".to_string(), vec![]),
             ParseEvent::End("grandson".to_string()),
-            ParseEvent::Start("grandson".to_string()),
+            ParseEvent::Start("grandson".to_string(), vec![]),
             ParseEvent::End("grandson".to_string()),
             ParseEvent::End("daughter".to_string()),
             ParseEvent::End("parent".to_string()),
@@ -361,4 +834,281 @@ mod test {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn cardinality_markers() {
+        let mut stream = vec![
+            ParseEvent::Start("catalog".to_string(), vec![]),
+            ParseEvent::Start("product".to_string(), vec![]),
+            ParseEvent::Start("name".to_string(), vec![]),
+            ParseEvent::End("name".to_string()),
+            ParseEvent::Start("price".to_string(), vec![]),
+            ParseEvent::End("price".to_string()),
+            ParseEvent::End("product".to_string()),
+            ParseEvent::Start("product".to_string(), vec![]),
+            ParseEvent::Start("name".to_string(), vec![]),
+            ParseEvent::End("name".to_string()),
+            ParseEvent::Start("name".to_string(), vec![]),
+            ParseEvent::End("name".to_string()),
+            ParseEvent::End("product".to_string()),
+            ParseEvent::End("catalog".to_string()),
+        ]
+        .into_iter();
+
+        let tree = parse(&mut stream);
+        let printed = tree.to_string();
+
+        // "name" is present in both products but repeated in the second: "+"
+        assert!(printed.contains("<name+ />"));
+        // "price" only shows up in the first product: "?"
+        assert!(printed.contains("<price? />"));
+    }
+
+    #[test]
+    fn events_node_with_uniform_children() {
+        let stream = vec![
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::End("parent".to_string()),
+        ]
+        .into_iter();
+
+        let actual: Vec<_> = parse_events(stream).collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                ThinEvent::Enter("parent".to_string()),
+                ThinEvent::Enter("son".to_string()),
+                ThinEvent::Exit("son".to_string()),
+                ThinEvent::Exit("parent".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn events_complex_tree() {
+        let stream = vec![
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("grandson".to_string(), vec![]),
+            ParseEvent::End("grandson".to_string()),
+            ParseEvent::Start("granddaughter".to_string(), vec![]),
+            ParseEvent::End("granddaughter".to_string()),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("granddaughter".to_string(), vec![]),
+            ParseEvent::End("granddaughter".to_string()),
+            ParseEvent::Start("granddaughter".to_string(), vec![]),
+            ParseEvent::End("granddaughter".to_string()),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::Start("daughter".to_string(), vec![]),
+            ParseEvent::Start("grandson".to_string(), vec![]),
+            ParseEvent::End("grandson".to_string()),
+            ParseEvent::Start("grandson".to_string(), vec![]),
+            ParseEvent::End("grandson".to_string()),
+            ParseEvent::End("daughter".to_string()),
+            ParseEvent::End("parent".to_string()),
+        ]
+        .into_iter();
+
+        let actual: Vec<_> = parse_events(stream).collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                ThinEvent::Enter("parent".to_string()),
+                ThinEvent::Enter("son".to_string()),
+                ThinEvent::Enter("grandson".to_string()),
+                ThinEvent::Exit("grandson".to_string()),
+                ThinEvent::Enter("granddaughter".to_string()),
+                ThinEvent::Exit("granddaughter".to_string()),
+                ThinEvent::Exit("son".to_string()),
+                ThinEvent::Enter("daughter".to_string()),
+                ThinEvent::Enter("grandson".to_string()),
+                ThinEvent::Exit("grandson".to_string()),
+                ThinEvent::Exit("daughter".to_string()),
+                ThinEvent::Exit("parent".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn interned_nodes_with_uniform_children() {
+        let mut stream = vec![
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::End("parent".to_string()),
+        ]
+        .into_iter();
+
+        let (root, count) = parse_interned(&mut stream);
+
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(count, 2); // "son" and "parent"
+    }
+
+    #[test]
+    fn interned_identical_subtrees_are_shared() {
+        let mut stream = vec![
+            ParseEvent::Start("doc".to_string(), vec![]),
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("grandchild".to_string(), vec![]),
+            ParseEvent::End("grandchild".to_string()),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::End("parent".to_string()),
+            ParseEvent::Start("cousin".to_string(), vec![]),
+            ParseEvent::Start("son".to_string(), vec![]),
+            ParseEvent::Start("grandchild".to_string(), vec![]),
+            ParseEvent::End("grandchild".to_string()),
+            ParseEvent::End("son".to_string()),
+            ParseEvent::End("cousin".to_string()),
+            ParseEvent::End("doc".to_string()),
+        ]
+        .into_iter();
+
+        let (root, count) = parse_interned(&mut stream);
+
+        let doc = &root.children["doc"];
+        let parent = &doc.children["parent"];
+        let cousin = &doc.children["cousin"];
+
+        assert!(Rc::ptr_eq(&parent.children["son"], &cousin.children["son"]));
+        assert_eq!(count, 5); // "grandchild", shared "son" shape, "parent", "cousin", "doc"
+    }
+
+    #[test]
+    fn distinct_tags_with_the_same_shape_are_not_interned_together() {
+        let mut stream = vec![
+            ParseEvent::Start("root".to_string(), vec![]),
+            ParseEvent::Start("loc".to_string(), vec!["priority".to_string()]),
+            ParseEvent::End("loc".to_string()),
+            ParseEvent::Start("lastmod".to_string(), vec!["changefreq".to_string()]),
+            ParseEvent::End("lastmod".to_string()),
+            ParseEvent::End("root".to_string()),
+        ]
+        .into_iter();
+
+        let tree = parse(&mut stream);
+        let root = &tree.children["root"];
+
+        assert!(!Rc::ptr_eq(&root.children["loc"], &root.children["lastmod"]));
+
+        let printed = tree.to_string();
+        assert!(printed.contains("<loc @priority />"));
+        assert!(printed.contains("<lastmod @changefreq />"));
+    }
+
+    #[test]
+    fn attributes_are_unioned_across_occurrences() {
+        let mut stream = vec![
+            ParseEvent::Start("urlset".to_string(), vec![]),
+            ParseEvent::Start("url".to_string(), vec![]),
+            ParseEvent::Start(
+                "loc".to_string(),
+                vec!["priority".to_string()],
+            ),
+            ParseEvent::End("loc".to_string()),
+            ParseEvent::End("url".to_string()),
+            ParseEvent::Start("url".to_string(), vec![]),
+            ParseEvent::Start(
+                "loc".to_string(),
+                vec!["changefreq".to_string()],
+            ),
+            ParseEvent::End("loc".to_string()),
+            ParseEvent::End("url".to_string()),
+            ParseEvent::End("urlset".to_string()),
+        ]
+        .into_iter();
+
+        let tree = parse(&mut stream);
+        let printed = tree.to_string();
+
+        assert!(printed.contains("<loc @changefreq @priority />"));
+    }
+
+    #[test]
+    fn valid_document_reports_no_errors() {
+        let mut stream = vec![
+            ParseEvent::Start("url".to_string(), vec![]),
+            ParseEvent::Start("loc".to_string(), vec![]),
+            ParseEvent::End("loc".to_string()),
+            ParseEvent::End("url".to_string()),
+        ]
+        .into_iter();
+
+        let schema = Schema::new().require("url", &["loc"]);
+        let errors = parse_validated(&mut stream, &schema);
+
+        assert_eq!(errors, vec![]);
+    }
+
+    #[test]
+    fn missing_and_unexpected_children_are_reported() {
+        let mut stream = vec![
+            ParseEvent::Start("urlset".to_string(), vec![]),
+            ParseEvent::Start("url".to_string(), vec![]),
+            ParseEvent::Start("image".to_string(), vec![]),
+            ParseEvent::End("image".to_string()),
+            ParseEvent::End("url".to_string()),
+            ParseEvent::End("urlset".to_string()),
+        ]
+        .into_iter();
+
+        let schema = Schema::new().require("url", &["loc"]);
+        let errors = parse_validated(&mut stream, &schema);
+
+        assert_eq!(
+            errors,
+            vec![ValidationError {
+                path: vec!["urlset".to_string(), "url".to_string()],
+                missing: vec!["loc".to_string()],
+                unexpected: vec!["image".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn load_schema_parses_tag_child_list_lines() {
+        let path = std::env::temp_dir().join("tree_thinning_test_schema.txt");
+        std::fs::write(&path, "# comment\nurlset: url\n\nurl: loc,lastmod\n").unwrap();
+
+        let schema = load_schema(path.to_str().unwrap());
+
+        assert_eq!(
+            schema.required_children.get("urlset"),
+            Some(&vec!["url".to_string()])
+        );
+        assert_eq!(
+            schema.required_children.get("url"),
+            Some(&vec!["loc".to_string(), "lastmod".to_string()])
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_roundtrip() {
+        let mut stream = vec![
+            ParseEvent::Start("parent".to_string(), vec![]),
+            ParseEvent::Start("child".to_string(), vec![]),
+            ParseEvent::End("child".to_string()),
+            ParseEvent::End("parent".to_string()),
+        ]
+        .into_iter();
+
+        let tree = parse(&mut stream);
+
+        let json = serde_json::to_string(&*tree).unwrap();
+        assert_eq!(json, r#"{"parent":{"child":{}}}"#);
+
+        let restored: Node = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, *tree);
+    }
 }